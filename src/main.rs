@@ -11,20 +11,182 @@ mod app {
     use teensy4_pins::common::*;
     use systick_monotonic::{fugit::Duration, Systick};
     use imxrt_iomuxc::prelude::*;
+    use imxrt_iomuxc::{DriveStrength, Pad, SlewRate};
+    use core::fmt::Write as _;
+    use heapless::{String, Vec};
 
     // define some associated types for loca struct definition
     type Led = gpio::Output<P13>;
     type Button = gpio::Input<P16>;
+    type Button2 = gpio::Input<P17>;
+    type Gpio1 = board::Gpio1;
+
+    /// Object-safe view over a debounced button pin: just the two ops
+    /// `int_toggle` needs to service the shared GPIO1_COMBINED_16_31
+    /// interrupt. `Button` and `Button2` are different concrete
+    /// `gpio::Input<P*>` types, so they can't sit in one homogeneous array
+    /// -- but a `&mut dyn ButtonPin` doesn't need `alloc`, only a place to
+    /// borrow from, so this is enough to make `int_toggle` genuinely data
+    /// driven: see the `registry` array built in `int_toggle`.
+    trait ButtonPin {
+        fn is_triggered(&mut self) -> bool;
+        fn clear_triggered(&mut self);
+    }
+
+    impl ButtonPin for Button {
+        fn is_triggered(&mut self) -> bool {
+            self.is_triggered()
+        }
+        fn clear_triggered(&mut self) {
+            self.clear_triggered()
+        }
+    }
+
+    impl ButtonPin for Button2 {
+        fn is_triggered(&mut self) -> bool {
+            self.is_triggered()
+        }
+        fn clear_triggered(&mut self) {
+            self.clear_triggered()
+        }
+    }
+
+    /// How a confirmed button edge affects the LED.
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum PressMode {
+        /// Flip the LED on each confirmed press; ignore release.
+        Toggle,
+        /// LED follows the button: on while held, off once released.
+        Momentary,
+        /// Each confirmed press cycles the LED through `RATE_TABLE`; the
+        /// `blink` task does the actual blinking.
+        Blink,
+    }
+
+    // blink periods (ms) cycled through by `PressMode::Blink`; `None` turns
+    // the LED off
+    const RATE_TABLE: [Option<u32>; 4] = [Some(1000), Some(500), Some(200), None];
+    // how often `blink` rechecks the mode/rate while idle (not blinking)
+    const BLINK_IDLE_RECHECK_MS: u32 = 200;
 
     #[local]
     struct Local {
-        led: Led,
+        // shift register used by `poll_button` to debounce the raw pin level
+        shift_reg: u16,
+        // shift register used by `poll_secondary_button`
+        shift_reg2: u16,
+        // pumps the USB logger/serial port; must be polled on its interrupt
+        poller: bsp::usb::Poller,
+        shell: Shell,
     }
 
     #[shared]
     struct Shared {
         pressed: bool,
         button: Button,
+        // second button on the same GPIO1 bank, serviced by the same
+        // combined interrupt as `button`; see `int_toggle`
+        button2: Button2,
+        gpio1: Gpio1,
+        mode: PressMode,
+        // mask applied to the debounce shift register; see `stable_mask`
+        stable_mask: u16,
+        // shared between `poll_button` (Toggle/Momentary) and `blink`
+        // (Blink), so it has to live behind a lock rather than as a Local
+        led: Led,
+        // mirrors the LED's hardware state so `state` queries don't need
+        // to lock `led` itself
+        led_on: bool,
+        press_count: u32,
+        // index into RATE_TABLE, advanced by a confirmed press in Blink mode
+        blink_rate_idx: usize,
+        // true while a debounce sampling chain is in flight, guards against
+        // re-spawning `poll_button` from a bouncy run of edge interrupts
+        sampling: bool,
+        // confirmed state/count for the secondary button, debounced by
+        // `poll_secondary_button`
+        secondary_pressed: bool,
+        secondary_press_count: u32,
+        secondary_sampling: bool,
+    }
+
+    // default number of consecutive 1ms samples that must agree before a
+    // pin transition is trusted; tunable at runtime via the `debounce`
+    // shell command (stable-time window = ticks * sample period)
+    const DEFAULT_STABLE_TICKS: u32 = 16;
+
+    /// Build the shift-register mask for a debounce window of `ticks`
+    /// consecutive samples (clamped to the register's 16-bit width).
+    fn stable_mask(ticks: u32) -> u16 {
+        if ticks >= 16 {
+            0xFFFF
+        } else {
+            ((1u32 << ticks) - 1) as u16
+        }
+    }
+
+    /// Electrical pad settings for a digital input pin: keeper, drive
+    /// strength, slew rate, and input hysteresis (Schmitt trigger) in one
+    /// place, instead of a one-off `Config::zero()` chain per pin.
+    /// Hardware-level filtering here complements the software debounce in
+    /// `poll_button`.
+    struct PadConfig {
+        keeper: Option<PullKeeper>,
+        drive_strength: DriveStrength,
+        slew_rate: SlewRate,
+        hysteresis: bool,
+    }
+
+    impl PadConfig {
+        /// Starting point for the builder: no keeper, drive strength R0
+        /// (the weakest enabled setting; `R0_n` is R0/n, i.e. stronger),
+        /// fast slew, no hysteresis.
+        const fn new() -> Self {
+            Self {
+                keeper: None,
+                drive_strength: DriveStrength::R0,
+                slew_rate: SlewRate::Fast,
+                hysteresis: false,
+            }
+        }
+
+        fn pull_keeper(mut self, keeper: PullKeeper) -> Self {
+            self.keeper = Some(keeper);
+            self
+        }
+
+        fn drive_strength(mut self, drive_strength: DriveStrength) -> Self {
+            self.drive_strength = drive_strength;
+            self
+        }
+
+        fn slew_rate(mut self, slew_rate: SlewRate) -> Self {
+            self.slew_rate = slew_rate;
+            self
+        }
+
+        /// Enable the pad's input Schmitt trigger so slow or noisy edges
+        /// snap cleanly between levels instead of chattering around the
+        /// switching threshold.
+        fn hysteresis(mut self, enabled: bool) -> Self {
+            self.hysteresis = enabled;
+            self
+        }
+    }
+
+    /// Apply a [`PadConfig`] to `pad` and hand it back ready to pass to
+    /// `gpio*.input(..)`. Reusable across every input pin instead of
+    /// copy-pasting a `Config::zero()` chain per pin.
+    fn configure_input_pad<P: Pad>(mut pad: P, cfg: PadConfig) -> P {
+        let mut config = Config::zero()
+            .set_drive_strength(cfg.drive_strength)
+            .set_slew_rate(cfg.slew_rate)
+            .set_hysteresis(cfg.hysteresis);
+        if let Some(keeper) = cfg.keeper {
+            config = config.set_pull_keeper(Some(keeper));
+        }
+        configure(&mut pad, config);
+        pad
     }
 
     #[monotonic(binds = SysTick, default = true)]
@@ -42,25 +204,96 @@ mod app {
             ..
         } = board::t40(cx.device);
         
-        // usb logging setup
-        bsp::LoggingFrontend::default_log().register_usb(usb);
+        // usb logging setup; the returned poller also pumps the same USB
+        // serial connection so the shell task below can read commands back
+        let poller = bsp::LoggingFrontend::default_log().register_usb(usb);
 
         // Init monotonic systick for delayed spawn
         let mono = Systick::new(cx.core.SYST, 36_000_000);
 
-        // configure pin 16 as an internal pull up
-        configure(&mut pins.p16, Config::zero().set_pull_keeper(Some(PullKeeper::Pullup22k)));
+        // configure pin 16 and pin 17 as internal pull-ups with the input
+        // Schmitt trigger enabled, a reduced drive strength, and a slow
+        // slew rate, so bounce and slow edges are filtered in hardware
+        // before software debounce ever sees them
+        let button_pad = configure_input_pad(
+            pins.p16,
+            PadConfig::new()
+                .pull_keeper(PullKeeper::Pullup22k)
+                .hysteresis(true)
+                .drive_strength(DriveStrength::R0_4)
+                .slew_rate(SlewRate::Slow),
+        );
+        let button2_pad = configure_input_pad(
+            pins.p17,
+            PadConfig::new()
+                .pull_keeper(PullKeeper::Pullup22k)
+                .hysteresis(true)
+                .drive_strength(DriveStrength::R0_4)
+                .slew_rate(SlewRate::Slow),
+        );
 
         let led = gpio2.output(pins.p13);
-        let button = gpio1.input(pins.p16);
+        let button = gpio1.input(button_pad);
+        let button2 = gpio1.input(button2_pad);
 
-        gpio1.set_interrupt(&button, Some(Trigger::FallingEdge));
+        let mode = PressMode::Toggle;
+        configure_trigger(&mut gpio1, &button, mode);
+        // only the falling (press) edge wakes this button's ISR;
+        // `poll_secondary_button` stays alive after a confirmed press and
+        // resamples until it observes the release itself, so the missing
+        // rising-edge interrupt doesn't latch `secondary_pressed`
+        gpio1.set_interrupt(&button2, Some(Trigger::FallingEdge));
 
         // set led to off
         led.clear();
 
+        // keep `blink` running from boot; it no-ops until PressMode::Blink
+        // is selected
+        blink::spawn().unwrap();
+
         // returned the initialized shared, local, and monotonic resources
-        (Shared {pressed: false, button}, Local {led}, init::Monotonics(mono))
+        (
+            Shared {
+                pressed: false,
+                button,
+                button2,
+                gpio1,
+                mode,
+                stable_mask: stable_mask(DEFAULT_STABLE_TICKS),
+                led,
+                led_on: false,
+                press_count: 0,
+                blink_rate_idx: 0,
+                sampling: false,
+                secondary_pressed: false,
+                secondary_press_count: 0,
+                secondary_sampling: false,
+            },
+            // Seed to the "stable released" value, not 0: a `poll_*` chain
+            // only ends on a confirmed release, which leaves the register
+            // all-ones, so every later chain starts from that same value.
+            // Starting from 0 would read as an instantly "confirmed press"
+            // with zero actual debounce depth.
+            Local {
+                shift_reg: stable_mask(DEFAULT_STABLE_TICKS),
+                shift_reg2: stable_mask(DEFAULT_STABLE_TICKS),
+                poller,
+                shell: Shell::new(),
+            },
+            init::Monotonics(mono),
+        )
+    }
+
+    /// Pick the interrupt trigger edge(s) for the given mode: `Toggle` and
+    /// `Blink` arm only the falling (press) edge, while `Momentary` also
+    /// arms the rising (release) edge. In every mode `poll_button` keeps
+    /// sampling after a confirmed press until it confirms the release.
+    fn configure_trigger(gpio1: &mut Gpio1, button: &Button, mode: PressMode) {
+        let trigger = match mode {
+            PressMode::Toggle | PressMode::Blink => Trigger::FallingEdge,
+            PressMode::Momentary => Trigger::EitherEdge,
+        };
+        gpio1.set_interrupt(button, Some(trigger));
     }
 
     #[idle]
@@ -70,63 +303,505 @@ mod app {
         }
     }
 
-    #[task(binds = GPIO1_COMBINED_16_31, local = [led], shared = [pressed, button])]
+    // Services every button on the GPIO1_COMBINED_16_31 bank interrupt.
+    // Locks every button resource once, then walks a `registry` array of
+    // (pin, its `sampling` flag, the software task that debounces it)
+    // built from `&mut dyn ButtonPin` trait objects -- adding a third
+    // button means a new `Shared` field, a `ButtonPin` impl for its pin
+    // type (or reusing an existing one), and one more entry in `registry`,
+    // not a new match arm here.
+    //
+    // For each entry whose pin actually raised its flag (not every button
+    // on the bank necessarily did), MUST clear that flag -- otherwise this
+    // becomes an infinite loop -- and kick off that button's own sampling
+    // chain, unless one is already in flight. The actual press/release
+    // decision is left to the matching `poll_*` task, which samples the
+    // raw pin on a steady tick rather than trusting a single (possibly
+    // bouncy) edge.
+    #[task(binds = GPIO1_COMBINED_16_31, shared = [button, button2, sampling, secondary_sampling])]
     fn int_toggle(cx: int_toggle::Context) {
-        // reference to shared resource
-        let mut pressed = cx.shared.pressed;
         let mut button = cx.shared.button;
+        let mut button2 = cx.shared.button2;
+        let mut sampling = cx.shared.sampling;
+        let mut secondary_sampling = cx.shared.secondary_sampling;
+
+        (button, button2, sampling, secondary_sampling).lock(
+            |button, button2, sampling, secondary_sampling| {
+                let registry: [(&mut dyn ButtonPin, &mut bool, fn() -> Result<(), ()>); 2] = [
+                    (button, sampling, poll_button::spawn),
+                    (button2, secondary_sampling, poll_secondary_button::spawn),
+                ];
 
-        // used for debounce routine 
-        // this specifies how long you must wait before being able to press the button again
-        let delay_500ms = Duration::<u64, 1, 1000>::from_ticks(5000);
-
-        // MUST clear irq flag
-        // If not done then int_toggle becomes an infinite loop
-        button.lock(|button| {
-            button.clear_triggered();
-        });
-
-        // acquire lock for pressed
-        pressed.lock(|pressed| {
-            // check if button hasn't been pressed
-            if *pressed == false {
-                // advertise that interrupt was triggered :)
-                log::info!("Interrupt was triggered!");
-                
-                // record that it's been pressed
+                for (pin, sampling, spawn) in registry {
+                    if !pin.is_triggered() {
+                        continue;
+                    }
+                    pin.clear_triggered();
+
+                    if !*sampling {
+                        *sampling = true;
+                        spawn().unwrap();
+                    }
+                }
+            },
+        );
+    }
+
+    // Periodic sampling debouncer: shifts the raw pin level into a shift
+    // register every tick and only acts once the register has settled to
+    // all-ones (stable release) or all-zeros (stable press), i.e. the pin
+    // held that level for the configured number of consecutive samples
+    // (see `stable_mask` / the `debounce` shell command).
+    //
+    // Note the pin is wired with a pull-up, so `button.is_set()` reads high
+    // (1) while released and low (0) while pressed: a 0x0000 shift register
+    // is a confirmed press, an all-ones mask a confirmed release.
+    #[task(local = [shift_reg], shared = [pressed, button, mode, stable_mask, led, led_on, press_count, blink_rate_idx, sampling])]
+    fn poll_button(cx: poll_button::Context) {
+        let sample_tick = Duration::<u64, 1, 1000>::from_ticks(1);
+
+        let mut button = cx.shared.button;
+        let mut pressed = cx.shared.pressed;
+        let mut mode = cx.shared.mode;
+        let mut sampling = cx.shared.sampling;
+        let mut led = cx.shared.led;
+        let mut led_on = cx.shared.led_on;
+
+        let mask = cx.shared.stable_mask.lock(|mask| *mask);
+        let level = button.lock(|button| button.is_set());
+        let shift_reg = cx.local.shift_reg;
+        *shift_reg = ((*shift_reg << 1) | (level as u16)) & mask;
+
+        if *shift_reg == 0 {
+            // confirmed press
+            let became_pressed = pressed.lock(|pressed| {
+                let edge = !*pressed;
                 *pressed = true;
+                edge
+            });
+
+            if became_pressed {
+                log::info!("debounced press");
+                cx.shared.press_count.lock(|count| *count += 1);
+
+                match mode.lock(|mode| *mode) {
+                    PressMode::Toggle => {
+                        led.lock(|led| led.toggle());
+                        led_on.lock(|on| *on = !*on);
+                    }
+                    PressMode::Momentary => {
+                        led.lock(|led| led.set());
+                        led_on.lock(|on| *on = true);
+                    }
+                    PressMode::Blink => {
+                        // Relies on `became_pressed` actually re-arming
+                        // after every press, not just the first one -- see
+                        // the spawn_after note below the match.
+                        cx.shared
+                            .blink_rate_idx
+                            .lock(|idx| *idx = (*idx + 1) % RATE_TABLE.len());
+                    }
+                }
+            }
 
-                // toggle Led
-                cx.local.led.toggle();
+            // Toggle/Blink only arm a FallingEdge interrupt (see
+            // `configure_trigger`), so nothing else will ever notice the
+            // button coming back up. Keep this chain alive past the
+            // confirmed press and resample until the release is confirmed
+            // too, instead of trusting a future edge interrupt to restart it.
+            poll_button::spawn_after(sample_tick).unwrap();
+        } else if *shift_reg == mask {
+            // confirmed release
+            pressed.lock(|pressed| *pressed = false);
 
-                // call the debounce routine
-                debounce::spawn_after(delay_500ms).unwrap();
-            } else {
-                // just another debug print
-                log::info!("bounce...");
+            if mode.lock(|mode| *mode) == PressMode::Momentary {
+                led.lock(|led| led.clear());
+                led_on.lock(|on| *on = false);
             }
-        });
+
+            sampling.lock(|sampling| *sampling = false);
+        } else {
+            // still bouncing; keep sampling
+            poll_button::spawn_after(sample_tick).unwrap();
+        }
     }
 
-    // debounce routine used to clear the pressed flag after a specified delay
-    #[task(shared = [pressed, button])]
-    fn debounce(cx: debounce::Context) {
-        // get reference to shared resource
-        let mut pressed = cx.shared.pressed;
+    // Same sampled debounce as `poll_button`, for the secondary (P17)
+    // button. It only reports presses (no modes/LED of its own) -- the
+    // point of this task is to show a second registry entry being
+    // dispatched and debounced independently of the primary button.
+    #[task(local = [shift_reg2], shared = [stable_mask, secondary_pressed, secondary_press_count, button2, secondary_sampling])]
+    fn poll_secondary_button(cx: poll_secondary_button::Context) {
+        let sample_tick = Duration::<u64, 1, 1000>::from_ticks(1);
+
+        let mut button2 = cx.shared.button2;
+        let mut pressed = cx.shared.secondary_pressed;
+        let mut sampling = cx.shared.secondary_sampling;
+
+        let mask = cx.shared.stable_mask.lock(|mask| *mask);
+        let level = button2.lock(|button2| button2.is_set());
+        let shift_reg = cx.local.shift_reg2;
+        *shift_reg = ((*shift_reg << 1) | (level as u16)) & mask;
+
+        if *shift_reg == 0 {
+            let became_pressed = pressed.lock(|pressed| {
+                let edge = !*pressed;
+                *pressed = true;
+                edge
+            });
+            if became_pressed {
+                log::info!("secondary button pressed");
+                cx.shared.secondary_press_count.lock(|count| *count += 1);
+            }
+
+            // Only a FallingEdge interrupt is armed for this button (see
+            // `init`), so keep this chain alive past the confirmed press
+            // and resample until the release is confirmed too.
+            poll_secondary_button::spawn_after(sample_tick).unwrap();
+        } else if *shift_reg == mask {
+            pressed.lock(|pressed| *pressed = false);
+            sampling.lock(|sampling| *sampling = false);
+        } else {
+            poll_secondary_button::spawn_after(sample_tick).unwrap();
+        }
+    }
+
+    // Free-running blinker for `PressMode::Blink`: re-spawns itself at the
+    // interval named by `RATE_TABLE[blink_rate_idx]`, so a press that
+    // advances the index takes effect on the *next* cycle. Idles (without
+    // touching the LED) whenever a different press mode is active.
+    #[task(shared = [led, led_on, mode, blink_rate_idx])]
+    fn blink(cx: blink::Context) {
+        let mut led = cx.shared.led;
+        let mut led_on = cx.shared.led_on;
+
+        let active = cx.shared.mode.lock(|mode| *mode) == PressMode::Blink;
+        let rate = if active {
+            RATE_TABLE[cx.shared.blink_rate_idx.lock(|idx| *idx)]
+        } else {
+            None
+        };
+
+        let next_delay_ms = match rate {
+            Some(period_ms) => {
+                led.lock(|led| led.toggle());
+                led_on.lock(|on| *on = !*on);
+                period_ms
+            }
+            None => {
+                if active {
+                    led.lock(|led| led.clear());
+                    led_on.lock(|on| *on = false);
+                }
+                BLINK_IDLE_RECHECK_MS
+            }
+        };
+
+        blink::spawn_after(Duration::<u64, 1, 1000>::from_ticks(next_delay_ms as u64)).unwrap();
+    }
+
+    // Switch the active `PressMode` at runtime and reconfigure the GPIO
+    // interrupt trigger to match (Momentary arms both edges, Toggle and
+    // Blink only the falling one).
+    #[task(shared = [mode, gpio1, button])]
+    fn set_press_mode(cx: set_press_mode::Context, new_mode: PressMode) {
+        let mut mode = cx.shared.mode;
+        let mut gpio1 = cx.shared.gpio1;
         let mut button = cx.shared.button;
 
-        // debug print
-        log::info!("debounced!");
+        mode.lock(|mode| *mode = new_mode);
+        (gpio1, button).lock(|gpio1, button| configure_trigger(gpio1, button, new_mode));
+    }
 
-        button.lock(|button| {
-            while button.is_set() {
-                // this accounts for the button being held high during the debounce routine
+    // Manually override the interrupt trigger edge, independent of the
+    // active press mode. Used by the `trigger` shell command.
+    #[task(shared = [gpio1, button])]
+    fn set_trigger(cx: set_trigger::Context, trigger: Trigger) {
+        let mut gpio1 = cx.shared.gpio1;
+        let mut button = cx.shared.button;
+
+        (gpio1, button).lock(|gpio1, button| gpio1.set_interrupt(button, Some(trigger)));
+    }
+
+    // Resize the debounce confirmation window. Used by the `debounce`
+    // shell command.
+    #[task(shared = [stable_mask])]
+    fn set_debounce(cx: set_debounce::Context, ticks: u32) {
+        let mut mask = cx.shared.stable_mask;
+        mask.lock(|mask| *mask = stable_mask(ticks));
+    }
+
+    // Command shell served over the same USB serial connection as the
+    // logger. Parses incoming bytes into a line buffer, dispatches
+    // recognized commands against `Shared`, and supports Tab-completion
+    // and an up-arrow history recall over the last few lines.
+    #[task(binds = USB_OTG1, local = [poller, shell], shared = [pressed, led_on, press_count, mode, secondary_press_count])]
+    fn usb_shell(cx: usb_shell::Context) {
+        let poller = cx.local.poller;
+        let shell = cx.local.shell;
+
+        let mut pressed = cx.shared.pressed;
+        let mut led_on = cx.shared.led_on;
+        let mut press_count = cx.shared.press_count;
+        let mut mode = cx.shared.mode;
+        let mut secondary_press_count = cx.shared.secondary_press_count;
+
+        poller.poll();
+
+        let mut buf = [0u8; 64];
+        while let Ok(n) = poller.read(&mut buf) {
+            if n == 0 {
+                break;
             }
-        });
-        
-        // acquire lock and clear button triggered and pressed 
-        pressed.lock(|pressed| {
-            *pressed = false;
-        });
+
+            for &byte in &buf[..n] {
+                let line = match shell.feed(byte, poller) {
+                    Some(line) => line,
+                    None => continue,
+                };
+
+                let mut parts = line.as_str().splitn(2, ' ');
+                let cmd = parts.next().unwrap_or("");
+                let arg = parts.next().unwrap_or("").trim();
+
+                match cmd {
+                    "" => {}
+                    "help" => shell.print_help(poller),
+                    "state" => {
+                        let on = led_on.lock(|on| *on);
+                        let is_pressed = pressed.lock(|p| *p);
+                        let active_mode = match mode.lock(|mode| *mode) {
+                            PressMode::Toggle => "toggle",
+                            PressMode::Momentary => "momentary",
+                            PressMode::Blink => "blink",
+                        };
+                        write!(poller, "led={} pressed={} mode={}", on, is_pressed, active_mode).ok();
+                    }
+                    "count" => {
+                        let count = press_count.lock(|count| *count);
+                        let secondary_count = secondary_press_count.lock(|count| *count);
+                        write!(
+                            poller,
+                            "presses={} secondary_presses={}",
+                            count, secondary_count
+                        )
+                        .ok();
+                    }
+                    "mode" => match parse_mode(arg) {
+                        Some(new_mode) => {
+                            set_press_mode::spawn(new_mode).unwrap();
+                        }
+                        None => {
+                            write!(poller, "usage: mode <toggle|momentary|blink>").ok();
+                        }
+                    },
+                    "trigger" => match parse_trigger(arg) {
+                        Some(trigger) => {
+                            set_trigger::spawn(trigger).unwrap();
+                        }
+                        None => {
+                            write!(poller, "usage: trigger <falling|rising|both>").ok();
+                        }
+                    },
+                    "debounce" => match arg.parse::<u32>() {
+                        Ok(ticks) if ticks >= 1 && ticks <= 16 => {
+                            set_debounce::spawn(ticks).unwrap();
+                        }
+                        _ => {
+                            write!(poller, "usage: debounce <1-16>").ok();
+                        }
+                    },
+                    other => {
+                        write!(poller, "unknown command: {} (try 'help')", other).ok();
+                    }
+                }
+
+                shell.history.push(line);
+                shell.prompt(poller);
+            }
+        }
+    }
+
+    const COMMANDS: &[(&str, &str)] = &[
+        ("help", "list available commands"),
+        ("state", "print the debounced button/LED state"),
+        ("count", "print the confirmed press counts of both buttons"),
+        ("mode", "mode <toggle|momentary|blink> - set the press mode"),
+        ("trigger", "trigger <falling|rising|both> - set the interrupt edge"),
+        ("debounce", "debounce <1-16> - set the debounce sample window"),
+    ];
+
+    fn parse_mode(arg: &str) -> Option<PressMode> {
+        match arg {
+            "toggle" => Some(PressMode::Toggle),
+            "momentary" | "keep" => Some(PressMode::Momentary),
+            "blink" => Some(PressMode::Blink),
+            _ => None,
+        }
+    }
+
+    fn parse_trigger(arg: &str) -> Option<Trigger> {
+        match arg {
+            "falling" => Some(Trigger::FallingEdge),
+            "rising" => Some(Trigger::RisingEdge),
+            "both" => Some(Trigger::EitherEdge),
+            _ => None,
+        }
+    }
+
+    const LINE_CAPACITY: usize = 64;
+    const HISTORY_DEPTH: usize = 8;
+    type ShellLine = String<LINE_CAPACITY>;
+
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum EscState {
+        None,
+        Esc,
+        Bracket,
+    }
+
+    /// Ring of previously submitted lines, most recent last, used to
+    /// implement up-arrow history recall.
+    struct ShellHistory {
+        lines: Vec<ShellLine, HISTORY_DEPTH>,
+        cursor: Option<usize>,
+    }
+
+    impl ShellHistory {
+        const fn new() -> Self {
+            Self {
+                lines: Vec::new(),
+                cursor: None,
+            }
+        }
+
+        fn push(&mut self, line: ShellLine) {
+            if line.is_empty() {
+                return;
+            }
+            if self.lines.is_full() {
+                self.lines.remove(0);
+            }
+            let _ = self.lines.push(line);
+            self.cursor = None;
+        }
+
+        /// Step one entry further back in history and return its text.
+        fn recall_older(&mut self) -> Option<&str> {
+            if self.lines.is_empty() {
+                return None;
+            }
+            let next = match self.cursor {
+                None => self.lines.len() - 1,
+                Some(0) => 0,
+                Some(i) => i - 1,
+            };
+            self.cursor = Some(next);
+            Some(self.lines[next].as_str())
+        }
+    }
+
+    /// Line editor + command dispatcher for the USB shell. Owns the
+    /// in-progress line buffer and the recall history; `usb_shell` drives
+    /// it one byte at a time and matches on the line it returns.
+    struct Shell {
+        line: ShellLine,
+        esc: EscState,
+        history: ShellHistory,
+    }
+
+    impl Shell {
+        const fn new() -> Self {
+            Self {
+                line: ShellLine::new(),
+                esc: EscState::None,
+                history: ShellHistory::new(),
+            }
+        }
+
+        fn prompt(&self, poller: &mut bsp::usb::Poller) {
+            write!(poller, "\r\n> ").ok();
+        }
+
+        fn redraw(&self, poller: &mut bsp::usb::Poller) {
+            write!(poller, "\r> {}", self.line.as_str()).ok();
+        }
+
+        fn print_help(&self, poller: &mut bsp::usb::Poller) {
+            for &(name, usage) in COMMANDS.iter() {
+                write!(poller, "\r\n{:<10} {}", name, usage).ok();
+            }
+        }
+
+        /// Feed one incoming byte into the line editor. Returns the
+        /// completed line once the user presses Enter.
+        fn feed(&mut self, byte: u8, poller: &mut bsp::usb::Poller) -> Option<ShellLine> {
+            match self.esc {
+                EscState::None if byte == 0x1b => {
+                    self.esc = EscState::Esc;
+                    return None;
+                }
+                EscState::Esc if byte == b'[' => {
+                    self.esc = EscState::Bracket;
+                    return None;
+                }
+                EscState::Bracket => {
+                    self.esc = EscState::None;
+                    if byte == b'A' {
+                        if let Some(recalled) = self.history.recall_older() {
+                            self.line = ShellLine::try_from(recalled).unwrap_or_default();
+                        }
+                        self.redraw(poller);
+                    }
+                    return None;
+                }
+                _ => self.esc = EscState::None,
+            }
+
+            match byte {
+                b'\r' | b'\n' => Some(core::mem::replace(&mut self.line, ShellLine::new())),
+                0x08 | 0x7f => {
+                    if self.line.pop().is_some() {
+                        write!(poller, "\x08 \x08").ok();
+                    }
+                    None
+                }
+                b'\t' => {
+                    self.autocomplete(poller);
+                    None
+                }
+                b if (0x20..0x7f).contains(&b) => {
+                    if self.line.push(b as char).is_ok() {
+                        write!(poller, "{}", b as char).ok();
+                    }
+                    None
+                }
+                _ => None,
+            }
+        }
+
+        fn autocomplete(&mut self, poller: &mut bsp::usb::Poller) {
+            let mut matches: Vec<&str, 8> = Vec::new();
+            for &(name, _) in COMMANDS.iter() {
+                if name.starts_with(self.line.as_str()) {
+                    let _ = matches.push(name);
+                }
+            }
+
+            match matches.len() {
+                0 => {}
+                1 => {
+                    self.line = ShellLine::try_from(matches[0]).unwrap_or_default();
+                    self.redraw(poller);
+                }
+                _ => {
+                    write!(poller, "\r\n").ok();
+                    for name in &matches {
+                        write!(poller, "{} ", name).ok();
+                    }
+                    self.redraw(poller);
+                }
+            }
+        }
     }
 }
\ No newline at end of file